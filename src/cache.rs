@@ -1,8 +1,211 @@
 // Copyright (c) The hUtils Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use std::collections::HashMap;
-use std::hash::Hash;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{BuildHasher, Hash, RandomState};
+use std::sync::{Arc, RwLock};
+
+/// `OwnedFnCacher` is like [`FnCacher`], but it stores its keys by value instead of by reference.
+/// This drops the `'a` lifetime tying the cacher to its caller's arguments, so it can be stored in
+/// structs, built from temporaries, and called with short-lived keys.
+///
+/// Built with [`OwnedFnCacher::with_capacity`], it evicts the oldest entry in FIFO order once it's
+/// full, which keeps a long-running cache from growing without bound.
+///
+/// ### Examples
+/// ```
+/// use hutils::OwnedFnCacher;
+///
+/// let mut square = OwnedFnCacher::new(|x: &i32| x * x);
+///
+/// assert_eq!(&9, square.call(&3));
+/// ```
+pub struct OwnedFnCacher<F, T, R>
+where
+    F: Fn(&T) -> R,
+    T: Eq + Hash + Clone,
+{
+    function: F,
+    results: HashMap<T, R>,
+    order: VecDeque<T>,
+    capacity: Option<usize>,
+}
+
+impl<F, T, R> OwnedFnCacher<F, T, R>
+where
+    F: Fn(&T) -> R,
+    T: Eq + Hash + Clone,
+{
+    /// `new` constructs an `OwnedFnCacher` with `function`. The cache grows without limit.
+    pub fn new(function: F) -> Self {
+        Self {
+            function,
+            results: HashMap::new(),
+            order: VecDeque::new(),
+            capacity: None,
+        }
+    }
+
+    /// `with_capacity` constructs an `OwnedFnCacher` with `function` that holds at most `max_entries`
+    /// results, evicting the oldest entry once it's full.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_entries` is `0`, since a cache with no room to hold even the entry it just
+    /// computed can't return a reference to it.
+    pub fn with_capacity(function: F, max_entries: usize) -> Self {
+        assert!(max_entries > 0, "OwnedFnCacher capacity must be at least 1");
+        Self {
+            function,
+            results: HashMap::with_capacity(max_entries),
+            order: VecDeque::with_capacity(max_entries),
+            capacity: Some(max_entries),
+        }
+    }
+
+    /// `call` returns the cached result if it exists in the cache.
+    /// Otherwise, it calls a function with `arg`, clones `arg` into the cache, evicts the oldest entry
+    /// if the new one took the cache past its capacity, and returns the result.
+    pub fn call(&mut self, arg: &T) -> &R {
+        if self.results.get(arg).is_none() {
+            let result = (self.function)(arg);
+            self.results.insert(arg.clone(), result);
+            self.order.push_back(arg.clone());
+
+            if let Some(capacity) = self.capacity {
+                while self.results.len() > capacity {
+                    if let Some(oldest) = self.order.pop_front() {
+                        self.results.remove(&oldest);
+                    }
+                }
+            }
+        }
+        &self.results[arg]
+    }
+
+    /// `len` returns the number of results currently held in the cache.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// `is_empty` returns `true` if the cache holds no results.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// `clear` drops every cached result.
+    pub fn clear(&mut self) {
+        self.results.clear();
+        self.order.clear();
+    }
+
+    /// `shrink_to_fit` shrinks the cache's backing storage to fit its current contents.
+    pub fn shrink_to_fit(&mut self) {
+        self.results.shrink_to_fit();
+        self.order.shrink_to_fit();
+    }
+}
+
+type RecurseFn<T, R, S> = dyn for<'f> Fn(&mut Recurse<'f, T, R, S>, &T) -> R;
+
+/// `Recurse` is the recursion handle passed to the function given to [`RecFnCacher::new`].
+/// Its own `call` checks the same cache as the surrounding `RecFnCacher`, so a subproblem
+/// computed during recursion is memoized exactly like the top-level call.
+///
+/// The function must not assume that the entry for the key currently being computed already
+/// exists, since doing so would recurse forever on a self-referential key.
+pub struct Recurse<'f, T, R, S = RandomState>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    function: &'f RecurseFn<T, R, S>,
+    results: &'f mut HashMap<T, R, S>,
+}
+
+impl<'f, T, R, S> Recurse<'f, T, R, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// `call` returns the cached result if it exists in the cache.
+    /// Otherwise, it recurses into the cached function with `arg` and returns the result.
+    pub fn call(&mut self, arg: &T) -> &R {
+        if !self.results.contains_key(arg) {
+            let function = self.function;
+            let result = function(&mut *self, arg);
+            self.results.insert(arg.clone(), result);
+        }
+        &self.results[arg]
+    }
+}
+
+/// `RecFnCacher` is like [`FnCacher`], but the cached function receives a [`Recurse`] handle
+/// alongside its argument, so subproblems it recurses into (Fibonacci, edit distance, recursive
+/// descent, ...) are memoized in the same cache. Re-entrant lookups during evaluation hit the
+/// shared table, so e.g. a naively-recursive Fibonacci runs in linear time instead of exponential.
+///
+/// ### Examples
+/// ```
+/// use hutils::{Recurse, RecFnCacher};
+///
+/// let mut fib = RecFnCacher::new(|r: &mut Recurse<u64, u64>, n: &u64| {
+///     if *n < 2 {
+///         *n
+///     } else {
+///         let a = *r.call(&(n - 1));
+///         let b = *r.call(&(n - 2));
+///         a + b
+///     }
+/// });
+///
+/// assert_eq!(&55, fib.call_rec(&10));
+/// ```
+pub struct RecFnCacher<T, R, S = RandomState>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    function: Box<RecurseFn<T, R, S>>,
+    results: HashMap<T, R, S>,
+}
+
+impl<T, R> RecFnCacher<T, R, RandomState>
+where
+    T: Eq + Hash + Clone,
+{
+    /// `new` constructs a `RecFnCacher` with `function`.
+    pub fn new<F>(function: F) -> Self
+    where
+        F: for<'f> Fn(&mut Recurse<'f, T, R, RandomState>, &T) -> R + 'static,
+    {
+        Self {
+            function: Box::new(function),
+            results: HashMap::new(),
+        }
+    }
+}
+
+impl<T, R, S> RecFnCacher<T, R, S>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// `call_rec` returns the cached result if it exists in the cache.
+    /// Otherwise, it calls the cached function with `arg`, passing it a recursion handle, and returns the result.
+    pub fn call_rec(&mut self, arg: &T) -> &R {
+        if !self.results.contains_key(arg) {
+            let function: &RecurseFn<T, R, S> = &*self.function;
+            let mut recurse = Recurse {
+                function,
+                results: &mut self.results,
+            };
+            let result = function(&mut recurse, arg);
+            self.results.insert(arg.clone(), result);
+        }
+        &self.results[arg]
+    }
+}
 
 /// `FnCacher` caches the result of a high-cost function using the design patterns of memoization and lazy evaluation.
 /// 
@@ -14,16 +217,16 @@ use std::hash::Hash;
 ///
 /// assert_eq!(&9, square.call(&3));
 /// ```
-pub struct FnCacher<'a, F, T, R>
+pub struct FnCacher<'a, F, T, R, S = RandomState>
 where
     F: Fn(&T) -> R,
     T: Eq + Hash,
 {
     function: F,
-    results: HashMap<&'a T, R>,
+    results: HashMap<&'a T, R, S>,
 }
 
-impl<'a, F, T, R> FnCacher<'a, F, T, R>
+impl<'a, F, T, R> FnCacher<'a, F, T, R, RandomState>
 where
     F: Fn(&T) -> R,
     T: Eq + Hash,
@@ -35,6 +238,23 @@ where
             results: HashMap::new(),
         }
     }
+}
+
+impl<'a, F, T, R, S> FnCacher<'a, F, T, R, S>
+where
+    F: Fn(&T) -> R,
+    T: Eq + Hash,
+    S: BuildHasher,
+{
+    /// `with_hasher` constructs a `FnCacher` with `function`, using `hasher` to build the underlying
+    /// `HashMap` instead of the default `RandomState`. This lets callers swap in a faster
+    /// non-cryptographic hasher for the tight inner-loop memoization this type is built for.
+    pub fn with_hasher(function: F, hasher: S) -> Self {
+        Self {
+            function,
+            results: HashMap::with_hasher(hasher),
+        }
+    }
 
     /// `call` returns the cached result if it exists in the cache.
     /// Otherwise, it calls a function with `arg` and returns the result.
@@ -47,6 +267,61 @@ where
     }
 }
 
+/// `SyncFnCacher` is like [`OwnedFnCacher`], but it caches behind a `RwLock` instead of requiring
+/// exclusive access, so it can be shared across threads and called from multiple `&self` methods.
+/// `call` takes a read lock to check for a hit; on a miss, it takes a write lock, rechecks to avoid
+/// racing another thread's computation of the same key, then computes, caches, and returns the result.
+///
+/// Results are returned as `Arc<R>` rather than `&R`, since a plain reference would stay tied to the
+/// lock guard that produced it.
+///
+/// ### Examples
+/// ```
+/// use hutils::SyncFnCacher;
+///
+/// let square = SyncFnCacher::new(|x: &i32| x * x);
+///
+/// assert_eq!(9, *square.call(&3));
+/// ```
+pub struct SyncFnCacher<F, T, R>
+where
+    F: Fn(&T) -> R,
+    T: Eq + Hash + Clone,
+{
+    function: F,
+    results: RwLock<HashMap<T, Arc<R>>>,
+}
+
+impl<F, T, R> SyncFnCacher<F, T, R>
+where
+    F: Fn(&T) -> R,
+    T: Eq + Hash + Clone,
+{
+    /// `new` constructs a `SyncFnCacher` with `function`.
+    pub fn new(function: F) -> Self {
+        Self {
+            function,
+            results: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// `call` returns the cached result if it exists in the cache.
+    /// Otherwise, it calls a function with `arg`, clones `arg` into the cache, and returns the result.
+    pub fn call(&self, arg: &T) -> Arc<R> {
+        if let Some(result) = self.results.read().unwrap().get(arg) {
+            return result.clone();
+        }
+
+        let mut results = self.results.write().unwrap();
+        if let Some(result) = results.get(arg) {
+            return result.clone();
+        }
+        let result = Arc::new((self.function)(arg));
+        results.insert(arg.clone(), result.clone());
+        result
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -80,4 +355,159 @@ mod tests {
         ];
         cases.iter().for_each(|case| assert_eq!(&case.1, cacher.call(&case.0)));
     }
+
+    #[test]
+    fn fn_cacher_with_hasher() {
+        let mut cacher = FnCacher::with_hasher(|x: &i32| x * x, RandomState::new());
+        let cases = vec![(1, 1), (2, 4), (3, 9)];
+        cases.iter().for_each(|case| assert_eq!(&case.1, cacher.call(&case.0)));
+    }
+
+    #[test]
+    fn owned_fn_cacher_i32() {
+        let mut cacher = OwnedFnCacher::new(|&x| x);
+        let cases = vec![(1, 1), (2, 2), (3, 3)];
+        cases.iter().for_each(|case| assert_eq!(&case.1, cacher.call(&case.0)));
+
+        let mut cacher = OwnedFnCacher::new(|x| x * x);
+        let cases = vec![(1, 1), (2, 4), (3, 9)];
+        cases.iter().for_each(|case| assert_eq!(&case.1, cacher.call(&case.0)));
+    }
+
+    #[test]
+    fn owned_fn_cacher_string() {
+        let mut cacher = OwnedFnCacher::new(|x: &String| x.clone());
+        let cases = vec![
+            ("a".to_string(), "a".to_string()),
+            ("b".to_string(), "b".to_string()),
+            ("c".to_string(), "c".to_string()),
+        ];
+        cases.iter().for_each(|case| assert_eq!(&case.1, cacher.call(&case.0)));
+
+        let mut cacher = OwnedFnCacher::new(|x: &String| x.len());
+        let cases = vec![
+            ("a".to_string(), 1),
+            ("bb".to_string(), 2),
+            ("ccc".to_string(), 3),
+        ];
+        cases.iter().for_each(|case| assert_eq!(&case.1, cacher.call(&case.0)));
+    }
+
+    #[test]
+    fn owned_fn_cacher_temporary_key() {
+        // Unlike `FnCacher`, `OwnedFnCacher` accepts keys that don't outlive the call.
+        let mut cacher = OwnedFnCacher::new(|x: &String| x.len());
+        for i in 0..3 {
+            assert_eq!(&i.to_string().len(), cacher.call(&i.to_string()));
+        }
+    }
+
+    #[test]
+    fn owned_fn_cacher_with_capacity_evicts_oldest() {
+        let mut cacher = OwnedFnCacher::with_capacity(|x: &i32| x * x, 2);
+        assert_eq!(&1, cacher.call(&1));
+        assert_eq!(&4, cacher.call(&2));
+        assert_eq!(2, cacher.len());
+
+        // `3` evicts `1`, the oldest entry.
+        assert_eq!(&9, cacher.call(&3));
+        assert_eq!(2, cacher.len());
+
+        let calls = std::cell::Cell::new(0);
+        let mut cacher = OwnedFnCacher::with_capacity(
+            |x: &i32| {
+                calls.set(calls.get() + 1);
+                x * x
+            },
+            2,
+        );
+        cacher.call(&1);
+        cacher.call(&2);
+        cacher.call(&3);
+        assert_eq!(3, calls.get());
+        // `1` was evicted, so calling it again recomputes rather than hitting the cache.
+        cacher.call(&1);
+        assert_eq!(4, calls.get());
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity must be at least 1")]
+    fn owned_fn_cacher_with_capacity_zero_panics() {
+        OwnedFnCacher::with_capacity(|x: &i32| x * x, 0);
+    }
+
+    #[test]
+    fn owned_fn_cacher_len_clear_shrink_to_fit() {
+        let mut cacher = OwnedFnCacher::new(|x: &i32| x * x);
+        assert!(cacher.is_empty());
+
+        cacher.call(&1);
+        cacher.call(&2);
+        assert_eq!(2, cacher.len());
+        assert!(!cacher.is_empty());
+
+        cacher.clear();
+        assert!(cacher.is_empty());
+
+        cacher.call(&1);
+        cacher.shrink_to_fit();
+        assert_eq!(1, cacher.len());
+    }
+
+    #[test]
+    fn rec_fn_cacher_fib() {
+        let mut fib = RecFnCacher::new(|r: &mut Recurse<u64, u64>, n: &u64| {
+            if *n < 2 {
+                *n
+            } else {
+                let a = *r.call(&(n - 1));
+                let b = *r.call(&(n - 2));
+                a + b
+            }
+        });
+        let cases = vec![(0, 0), (1, 1), (5, 5), (10, 55)];
+        cases.iter().for_each(|case| assert_eq!(&case.1, fib.call_rec(&case.0)));
+    }
+
+    #[test]
+    fn rec_fn_cacher_fib_memoizes() {
+        let calls = std::rc::Rc::new(std::cell::Cell::new(0));
+        let calls_handle = calls.clone();
+        let mut fib = RecFnCacher::new(move |r: &mut Recurse<u64, u64>, n: &u64| {
+            calls_handle.set(calls_handle.get() + 1);
+            if *n < 2 {
+                *n
+            } else {
+                let a = *r.call(&(n - 1));
+                let b = *r.call(&(n - 2));
+                a + b
+            }
+        });
+        fib.call_rec(&20);
+        // One closure invocation per distinct subproblem (0..=20), not the 21_891 a
+        // non-memoizing recursive Fibonacci would make.
+        assert_eq!(21, calls.get());
+    }
+
+    #[test]
+    fn sync_fn_cacher_i32() {
+        let cacher = SyncFnCacher::new(|x: &i32| x * x);
+        let cases = vec![(1, 1), (2, 4), (3, 9)];
+        cases.iter().for_each(|case| assert_eq!(case.1, *cacher.call(&case.0)));
+    }
+
+    #[test]
+    fn sync_fn_cacher_shared_across_threads() {
+        let cacher = Arc::new(SyncFnCacher::new(|x: &i32| x * x));
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let cacher = cacher.clone();
+                std::thread::spawn(move || (0..100).map(|x| *cacher.call(&x)).sum::<i32>())
+            })
+            .collect();
+        let expected: i32 = (0..100).map(|x| x * x).sum();
+        for handle in handles {
+            assert_eq!(expected, handle.join().unwrap());
+        }
+    }
 }
\ No newline at end of file
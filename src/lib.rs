@@ -5,4 +5,4 @@
 
 pub mod cache;
 
-pub use self::cache::FnCacher;
+pub use self::cache::{FnCacher, OwnedFnCacher, RecFnCacher, Recurse, SyncFnCacher};